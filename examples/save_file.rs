@@ -21,7 +21,6 @@ pub fn main() {
         },
         Err(error) => {
             println!("{:?}", error);
-            return
         }
     };
 }
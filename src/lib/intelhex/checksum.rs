@@ -0,0 +1,78 @@
+const CRC32_POLY: u32 = 0xEDB8_8320;
+
+const CRC32_TABLE: [u32; 256] = {
+    let mut table = [0u32; 256];
+    let mut i = 0;
+
+    while i < 256 {
+        let mut crc = i as u32;
+        let mut bit = 0;
+
+        while bit < 8 {
+            crc = if crc & 1 != 0 { (crc >> 1) ^ CRC32_POLY } else { crc >> 1 };
+            bit += 1;
+        }
+
+        table[i] = crc;
+        i += 1;
+    }
+
+    table
+};
+
+fn crc32_update(state: u32, data: &[u8]) -> u32 {
+    data.iter().fold(state, |crc, byte| {
+        CRC32_TABLE[((crc ^ *byte as u32) & 0xFF) as usize] ^ (crc >> 8)
+    })
+}
+
+/// CRC-32 (IEEE 802.3, as used by zlib) of `data`.
+pub fn crc32(data: &[u8]) -> u32 {
+    return crc32_update(0xFFFF_FFFF, data) ^ 0xFFFF_FFFF;
+}
+
+/// Incremental CRC-32 that can be queried after any number of bytes, so a
+/// partial transfer can be checked against a device's running CRC.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct RollingChecksum {
+    state: u32,
+    len: usize
+}
+
+impl RollingChecksum {
+    pub fn new() -> Self {
+        RollingChecksum {
+            state: 0xFFFF_FFFF,
+            len: 0
+        }
+    }
+
+    pub fn update(&mut self, data: &[u8]) {
+        self.state = crc32_update(self.state, data);
+        self.len += data.len();
+    }
+
+    /// CRC-32 of all bytes fed so far.
+    pub fn value(&self) -> u32 {
+        self.state ^ 0xFFFF_FFFF
+    }
+
+    /// Number of bytes fed so far.
+    pub fn len(&self) -> usize {
+        self.len
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.len == 0
+    }
+
+    pub fn reset(&mut self) {
+        *self = Self::new();
+    }
+}
+
+impl Default for RollingChecksum {
+    fn default() -> Self {
+        Self::new()
+    }
+}
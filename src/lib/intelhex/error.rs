@@ -49,7 +49,11 @@ pub enum IHexError {
     FileBadRecord,
     FileErrorLoad,
     FileErrorOpen,
-    FileErrorWrite
+    FileErrorWrite,
+    FileLineTooLong,
+    ImageOutOfRange,
+    ImageBadEncoding,
+    ImageUnpopulated
 }
 
 impl IHexError {
@@ -1,4 +1,6 @@
-use std::fs::{read_to_string, write};
+use std::collections::BTreeMap;
+use std::fs::{File, write};
+use std::io::{BufRead, BufReader, Read};
 use std::fmt::Debug;
 
 use bytes::{Bytes, BytesMut, BufMut};
@@ -6,16 +8,27 @@ use bytes::{Bytes, BytesMut, BufMut};
 use hex;
 use hex::ToHex;
 
+use sha2::{Digest, Sha256};
+
+#[cfg(feature = "base64")]
+use base64::{Engine, engine::general_purpose::STANDARD as BASE64};
+
 use crate::util::twos_comp;
+use crate::checksum::RollingChecksum;
 use crate::error::{IntelHexError, IHexError};
 
 const RECORD_START: char = ':';
+const DEFAULT_RECORD_LEN: u8 = 16;
 
-#[derive(Debug)]
+#[cfg(feature = "base64")]
+const BASE64_MAGIC: &[u8; 4] = b"IHX1";
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
 pub enum RecordType {
     Data,
     EndOfFile,
     ExtendedSegmentAddress,
+    StartSegmentAddress,
     ExtendedLinearAddress,
     StartLinearAddress
 }
@@ -27,6 +40,7 @@ impl RecordType {
             "00" => Ok(Self::Data),
             "01" => Ok(Self::EndOfFile),
             "02" => Ok(Self::ExtendedSegmentAddress),
+            "03" => Ok(Self::StartSegmentAddress),
             "04" => Ok(Self::ExtendedLinearAddress),
             "05" => Ok(Self::StartLinearAddress),
             _   => Err(IHexError::RecordInvalidType.new(
@@ -40,14 +54,62 @@ impl RecordType {
             Self::Data => 0,
             Self::EndOfFile => 1,
             Self::ExtendedSegmentAddress => 2,
+            Self::StartSegmentAddress => 3,
             Self::ExtendedLinearAddress => 4,
             Self::StartLinearAddress => 5
         }
     }
 }
 
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Endian {
+    Little,
+    Big
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum BudgetStatus {
+    Ok,
+    Warn { used: usize, max: usize },
+    Over { used: usize, max: usize }
+}
+
+/// What `canonicalize_with_report` changed. Indices refer to the records
+/// before canonicalization.
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct CanonicalizeReport {
+    /// Records that contribute nothing to the canonical layout.
+    pub removed: Vec<usize>,
+    /// Records whose data were combined into a single record, one entry
+    /// per canonical record listing exactly the records it draws from.
+    pub merged: Vec<Vec<usize>>,
+    /// Data records whose data now spans several records.
+    pub split: Vec<usize>,
+    /// Base address records with no counterpart in the original layout.
+    pub base_records_added: usize
+}
+
+impl CanonicalizeReport {
+    pub fn is_unchanged(&self) -> bool {
+        self.removed.is_empty()
+            && self.merged.is_empty()
+            && self.split.is_empty()
+            && self.base_records_added == 0
+    }
+}
+
+/// Base address records emitted when authoring data.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum AddressingMode {
+    /// Type-04 extended linear address records.
+    #[default]
+    Linear,
+    /// Type-02 extended segment address records, for real-mode loaders.
+    Segment
+}
+
 #[allow(unused)]
-#[derive(Debug)]
+#[derive(Debug, Clone)]
 pub struct Record {
     pub len: u8,
     pub addr: u16,
@@ -58,8 +120,25 @@ pub struct Record {
 
 #[allow(unused)]
 impl Record {
+    /// Panics if `data` is longer than 255 bytes.
+    pub fn new(rtype: RecordType, addr: u16, data: Bytes) -> Self {
+        assert!(data.len() <= u8::MAX as usize, "record data exceeds 255 bytes");
+
+        let mut record = Record {
+            len: data.len() as u8,
+            addr,
+            rtype,
+            data,
+            checksum: 0
+        };
+
+        record.checksum = record.calculate_checksum();
+
+        return record;
+    }
+
     pub fn parse(line: &str) -> Result<Option<Self>, IntelHexError> {
-        let start = match line.find(|b| b == RECORD_START) {
+        let start = match line.find(RECORD_START) {
             Some(index) => index,
             None => return Ok(None)
         };
@@ -79,14 +158,14 @@ impl Record {
         let data_end = 8 + (len as usize * 2);
         let record_end = 2 + data_end;
 
-        if !(record_end <= record_str.len()) {
+        if record_end > record_str.len() {
             return Err(IHexError::RecordInvalidLength.new(&format!(
                 "Record length: {}, expected: {}", record_str.len(), record_end,
             )))
         }
 
         let record = Record {
-            len: len,
+            len,
 
             addr: match hex::decode(&record_str[2..6]) {
                 Ok(byts) => (byts[0] as u16 * 256) + byts[1] as u16,
@@ -157,6 +236,31 @@ impl Record {
         return hex_str;
     }
     
+    /// Recomputes `len` and `checksum` after `data` or `addr` was changed.
+    ///
+    /// Panics if `data` is longer than 255 bytes.
+    pub fn update_checksum(&mut self) {
+        assert!(self.data.len() <= u8::MAX as usize, "record data exceeds 255 bytes");
+
+        self.len = self.data.len() as u8;
+        self.checksum = self.calculate_checksum();
+    }
+
+    /// Base address set by an extended segment/linear address record.
+    fn base_address(&self) -> Option<u32> {
+        if self.data.len() != 2 {
+            return None;
+        }
+
+        let value = u16::from_be_bytes([self.data[0], self.data[1]]) as u32;
+
+        match self.rtype {
+            RecordType::ExtendedSegmentAddress => Some(value << 4),
+            RecordType::ExtendedLinearAddress => Some(value << 16),
+            _ => None
+        }
+    }
+
     pub fn calculate_checksum(&self) -> u8 {
         let byts = self.to_bytes();
         twos_comp(
@@ -169,7 +273,134 @@ impl Record {
 
 }
 
+/// Fails if `len` bytes starting at `addr` would run past the 32-bit
+/// address space.
+fn check_address_range(addr: u32, len: usize) -> Result<(), IntelHexError> {
+    if addr as u64 + len as u64 > 0x1_0000_0000 {
+        return Err(IHexError::ImageOutOfRange.new(&format!(
+            "Data 0x{:X} + 0x{:X} exceeds the 32-bit address space", addr, len
+        )))
+    }
+
+    return Ok(());
+}
+
+/// Data records for `data` starting at `addr`, at most `record_len` bytes
+/// each and never crossing a 64K boundary. An extended linear address
+/// record is emitted whenever `base` (the base in effect, if known) differs
+/// from the one required.
+fn encode_data(
+    addr: u32, data: &[u8], record_len: u8, base: &mut Option<u32>
+) -> Vec<Record> {
+    assert!(record_len > 0, "record length must be non-zero");
+
+    let mut records = Vec::<Record>::new();
+    let mut offset: usize = 0;
+
+    while offset < data.len() {
+        let address = addr.wrapping_add(offset as u32);
+        let bank = address & 0xFFFF_0000;
+
+        if *base != Some(bank) {
+            records.push(Record::new(
+                RecordType::ExtendedLinearAddress,
+                0,
+                Bytes::copy_from_slice(&((bank >> 16) as u16).to_be_bytes())
+            ));
+            *base = Some(bank);
+        }
+
+        let len = (record_len as usize)
+            .min(data.len() - offset)
+            .min(0x1_0000 - (address & 0xFFFF) as usize);
+
+        records.push(Record::new(
+            RecordType::Data,
+            (address & 0xFFFF) as u16,
+            Bytes::copy_from_slice(&data[offset..offset + len])
+        ));
+
+        offset += len;
+    }
+
+    return records;
+}
+
+#[derive(Debug, Clone, Default)]
+pub struct ParseOptions {
+    /// Longest accepted line in bytes, excluding the line terminator.
+    pub max_line_len: Option<usize>
+}
+
+impl ParseOptions {
+    fn check_line(&self, line_no: usize, len: usize) -> Option<IntelHexError> {
+        match self.max_line_len {
+            Some(max) if len > max => Some(IHexError::FileLineTooLong.new(
+                &format!("Line {} exceeds maximum length of {} bytes", line_no, max)
+            )),
+            _ => None
+        }
+    }
+}
+
+/// Highest address reachable with extended segment addressing
+/// (segment 0xFFFF, offset 0xFFFF).
+const SEGMENT_ADDRESS_MAX: u64 = 0x10_FFEF;
+
+/// Data records for `data` starting at `addr`, like `encode_data` but using
+/// extended segment address records (base = segment << 4). A new segment
+/// is chosen whenever the next byte is out of the current one's 64K reach.
+fn encode_segmented_data(
+    addr: u32, data: &[u8], record_len: u8, base: &mut Option<u32>
+) -> Result<Vec<Record>, IntelHexError> {
+    assert!(record_len > 0, "record length must be non-zero");
+
+    let last = addr as u64 + data.len().saturating_sub(1) as u64;
+    if !data.is_empty() && last > SEGMENT_ADDRESS_MAX {
+        return Err(IHexError::ImageOutOfRange.new(&format!(
+            "Address 0x{:X} is beyond the reach of segment addressing", last
+        )))
+    }
+
+    let mut records = Vec::<Record>::new();
+    let mut offset: usize = 0;
+
+    while offset < data.len() {
+        let address = addr + offset as u32;
+
+        let segment_base = match *base {
+            Some(b) if address >= b && address - b <= 0xFFFF => b,
+            _ => {
+                let segment = (address >> 4).min(0xFFFF) as u16;
+                records.push(Record::new(
+                    RecordType::ExtendedSegmentAddress,
+                    0,
+                    Bytes::copy_from_slice(&segment.to_be_bytes())
+                ));
+                *base = Some((segment as u32) << 4);
+                (segment as u32) << 4
+            }
+        };
+
+        let record_addr = address - segment_base;
+        let len = (record_len as usize)
+            .min(data.len() - offset)
+            .min(0x1_0000 - record_addr as usize);
+
+        records.push(Record::new(
+            RecordType::Data,
+            record_addr as u16,
+            Bytes::copy_from_slice(&data[offset..offset + len])
+        ));
+
+        offset += len;
+    }
+
+    return Ok(records);
+}
+
 #[allow(unused)]
+#[derive(Clone)]
 pub struct IntelHexFile {
     pub path: Option<String>,
     pub size: usize,
@@ -178,10 +409,16 @@ pub struct IntelHexFile {
 
 #[allow(unused)]
 impl IntelHexFile {
-    fn parse_records(raw_data: &str) -> Result<Vec<Record>, IntelHexError> {
+    fn parse_records(
+        raw_data: &str, options: &ParseOptions
+    ) -> Result<Vec<Record>, IntelHexError> {
         let mut records = Vec::<Record>::new();
 
         for (i, line) in raw_data.lines().enumerate() {
+            if let Some(err) = options.check_line(i + 1, line.len()) {
+                return Err(err);
+            }
+
             let record_opt = match Record::parse(line) {
                 Ok(opt) => opt,
                 Err(err) => return Err(IHexError::FileBadRecord.new(
@@ -199,10 +436,16 @@ impl IntelHexFile {
     }
 
     pub fn load(raw_data: &str) -> Result<Self, IntelHexError> {
+        Self::load_with_options(raw_data, &ParseOptions::default())
+    }
+
+    pub fn load_with_options(
+        raw_data: &str, options: &ParseOptions
+    ) -> Result<Self, IntelHexError> {
         Ok(Self {
             path: None,
-            size: raw_data.bytes().len(),
-            records: match Self::parse_records(&raw_data) {
+            size: raw_data.len(),
+            records: match Self::parse_records(raw_data, options) {
                 Ok(records) => records,
                 Err(err) => return Err(IHexError::FileErrorLoad.new(
                     "Error loading data"
@@ -212,14 +455,59 @@ impl IntelHexFile {
     }
 
     pub fn load_file(path: &str) -> Result<Self, IntelHexError> {
-        let raw_data: String = match read_to_string(path) {
-            Ok(string) => string,
+        Self::load_file_with_options(path, &ParseOptions::default())
+    }
+
+    /// Like `load_file`, but reads line by line so that `max_line_len` is
+    /// enforced before an oversized line is buffered in full.
+    pub fn load_file_with_options(
+        path: &str, options: &ParseOptions
+    ) -> Result<Self, IntelHexError> {
+        let mut reader = match File::open(path) {
+            Ok(file) => BufReader::new(file),
             Err(err) => return Err(IHexError::FileErrorOpen.new(
                 &format!("Error opening file: {}", path)
             ).set_source(Box::new(err)))
         };
 
-        let mut intel_hex_file = match Self::load(&raw_data) {
+        // Room for the longest allowed line plus a CRLF terminator.
+        let limit = options.max_line_len
+            .map(|max| max as u64 + 2)
+            .unwrap_or(u64::MAX);
+
+        let mut raw_data = String::new();
+        let mut line = Vec::<u8>::new();
+        let mut line_no: usize = 0;
+
+        loop {
+            line.clear();
+
+            match (&mut reader).take(limit).read_until(b'\n', &mut line) {
+                Ok(0) => break,
+                Ok(_) => line_no += 1,
+                Err(err) => return Err(IHexError::FileErrorOpen.new(
+                    &format!("Error reading file: {}", path)
+                ).set_source(Box::new(err)))
+            }
+
+            let content = line.strip_suffix(b"\n").unwrap_or(&line);
+            let content = content.strip_suffix(b"\r").unwrap_or(content);
+
+            if let Some(err) = options.check_line(line_no, content.len()) {
+                return Err(IHexError::FileErrorLoad.new(
+                    "Error loading data"
+                ).set_source(Box::new(err)))
+            }
+
+            match std::str::from_utf8(&line) {
+                Ok(string) => raw_data += string,
+                Err(err) => return Err(IHexError::FileErrorOpen.new(
+                    &format!("Error reading file: {}", path)
+                ).set_source(Box::new(err)))
+            }
+        }
+
+        let mut intel_hex_file = match Self::load_with_options(&raw_data, options) {
             Ok(file) => file,
             Err(err_box) => return Err(err_box)
         };
@@ -229,6 +517,61 @@ impl IntelHexFile {
         return Ok(intel_hex_file)
     }
 
+    /// Builds a file holding `data` at `addr`, followed by an EOF record.
+    /// Fails if the data would run past the 32-bit address space.
+    pub fn from_binary(addr: u32, data: &[u8]) -> Result<Self, IntelHexError> {
+        Self::from_binary_with_mode(addr, data, AddressingMode::Linear)
+    }
+
+    /// Like `from_binary`, emitting base address records per `mode`.
+    pub fn from_binary_with_mode(
+        addr: u32, data: &[u8], mode: AddressingMode
+    ) -> Result<Self, IntelHexError> {
+        let mut intel_hex_file = Self {
+            path: None,
+            size: 0,
+            records: vec![Record::new(RecordType::EndOfFile, 0, Bytes::new())]
+        };
+
+        match intel_hex_file.push_data(addr, data, mode) {
+            Ok(_) => (),
+            Err(err) => return Err(err)
+        }
+
+        intel_hex_file.size = intel_hex_file.to_hex_str().len();
+
+        return Ok(intel_hex_file);
+    }
+
+    /// Adds data records for `data` at `addr` ahead of the final EOF record,
+    /// with whatever base address records `mode` requires.
+    pub fn push_data(
+        &mut self, addr: u32, data: &[u8], mode: AddressingMode
+    ) -> Result<(), IntelHexError> {
+        match check_address_range(addr, data.len()) {
+            Ok(_) => (),
+            Err(err) => return Err(err)
+        }
+
+        let mut base = Some(self.base_at(self.insertion_index()));
+
+        let records = match mode {
+            AddressingMode::Linear => {
+                encode_data(addr, data, DEFAULT_RECORD_LEN, &mut base)
+            },
+            AddressingMode::Segment => {
+                match encode_segmented_data(addr, data, DEFAULT_RECORD_LEN, &mut base) {
+                    Ok(records) => records,
+                    Err(err) => return Err(err)
+                }
+            }
+        };
+
+        self.insert_records(records);
+
+        return Ok(());
+    }
+
     pub fn binary_size(&self) -> usize {
         self.records.iter().map(|r| r.binary_size()).sum()
     }
@@ -257,10 +600,922 @@ impl IntelHexFile {
         return hex_str;
     }
     
+    /// SHA-256 of the binary record stream (`to_bytes`).
+    pub fn sha256(&self) -> [u8; 32] {
+        Sha256::digest(self.to_bytes()).into()
+    }
+
+    /// Short hex digest of the canonicalized image: the first 8 bytes of
+    /// its `sha256`. It depends only on the resolved content and start
+    /// address, not on the record layout or text of the file. The start
+    /// address is hashed as a linear entry point, so start segment and
+    /// start linear records naming the same address fingerprint alike.
+    pub fn fingerprint(&self) -> String {
+        let mut canonical = self.clone();
+        canonical.canonicalize();
+
+        if let Some(entry) = canonical.start_address() {
+            for record in canonical.records.iter_mut() {
+                if record.rtype == RecordType::StartSegmentAddress {
+                    *record = Record::new(
+                        RecordType::StartLinearAddress,
+                        0,
+                        Bytes::copy_from_slice(&entry.to_be_bytes())
+                    );
+                }
+            }
+        }
+
+        return (&canonical.sha256()[..8]).encode_hex_upper::<String>();
+    }
+
     pub fn get_path(&self) -> String {
         self.path.clone().unwrap_or("(none)".to_string())
     }
 
+    /// Applies `f` to every record, then recomputes the length and checksum
+    /// of each record whose type, address or data was changed.
+    ///
+    /// Panics if `f` leaves a record with more than 255 bytes of data.
+    pub fn for_each_record_mut(&mut self, mut f: impl FnMut(&mut Record)) {
+        for record in self.records.iter_mut() {
+            let rtype = record.rtype;
+            let addr = record.addr;
+            let data = record.data.clone();
+
+            f(record);
+
+            if record.rtype != rtype || record.addr != addr || record.data != data {
+                record.update_checksum();
+            }
+        }
+    }
+
+    /// Rebuilds the records from the resolved image: one run of data
+    /// records per segment with only the extended linear address records
+    /// they need, followed by the last start address record (if any) and a
+    /// single EOF record.
+    pub fn canonicalize(&mut self) {
+        self.canonicalize_with_report();
+    }
+
+    /// `canonicalize`, reporting how the original records map onto the
+    /// canonical ones.
+    pub fn canonicalize_with_report(&mut self) -> CanonicalizeReport {
+        let mut report = CanonicalizeReport::default();
+
+        let start_index = self.records
+            .iter()
+            .rposition(|r| matches!(
+                r.rtype,
+                RecordType::StartSegmentAddress | RecordType::StartLinearAddress
+            ));
+        let eof_index = self.records
+            .iter()
+            .rposition(|r| r.rtype == RecordType::EndOfFile);
+
+        // Original record supplying each resolved byte.
+        let mut origins = BTreeMap::<u32, usize>::new();
+        for (i, start) in self.resolved_data_records() {
+            for offset in 0..self.records[i].data.len() {
+                origins.insert(start.wrapping_add(offset as u32), i);
+            }
+        }
+
+        let mut records = Vec::<Record>::new();
+        let mut base = Some(0);
+
+        for (addr, data) in self.segments() {
+            records.extend(encode_data(addr, &data, DEFAULT_RECORD_LEN, &mut base));
+        }
+
+        let mut used = BTreeMap::<usize, usize>::new();
+        let mut current: u32 = 0;
+        // Original base records not yet matched by an identical (same type
+        // and value) canonical one.
+        let mut original_bases: Vec<usize> = self.records
+            .iter()
+            .enumerate()
+            .filter(|(_, r)| r.base_address().is_some())
+            .map(|(i, _)| i)
+            .collect();
+
+        for record in &records {
+            if let Some(address) = record.base_address() {
+                current = address;
+                let matching = original_bases.iter().position(|i| {
+                    self.records[*i].rtype == record.rtype
+                        && self.records[*i].data == record.data
+                });
+
+                match matching {
+                    Some(pos) => { original_bases.remove(pos); },
+                    None => report.base_records_added += 1
+                }
+                continue
+            }
+
+            let start = current + record.addr as u32;
+            let mut sources: Vec<usize> = (0..record.data.len() as u32)
+                .filter_map(|offset| origins.get(&(start + offset)).copied())
+                .collect();
+            sources.sort_unstable();
+            sources.dedup();
+
+            for source in &sources {
+                *used.entry(*source).or_insert(0) += 1;
+            }
+
+            if sources.len() > 1 {
+                report.merged.push(sources);
+            }
+        }
+
+        for (i, record) in self.records.iter().enumerate() {
+            let removed = match record.rtype {
+                RecordType::Data => !used.contains_key(&i),
+                RecordType::EndOfFile => Some(i) != eof_index,
+                RecordType::StartSegmentAddress | RecordType::StartLinearAddress => {
+                    Some(i) != start_index
+                },
+                _ => original_bases.contains(&i)
+            };
+
+            if removed {
+                report.removed.push(i);
+            }
+        }
+
+        report.split = used
+            .into_iter()
+            .filter(|(_, count)| *count > 1)
+            .map(|(i, _)| i)
+            .collect();
+
+        records.extend(start_index.map(|i| self.records[i].clone()));
+        records.push(Record::new(RecordType::EndOfFile, 0, Bytes::new()));
+
+        self.records = records;
+
+        return report;
+    }
+
+    /// Rebuilds the records with `f` applied to each data record's absolute
+    /// address and data, dropping those for which it returns `None`. Other
+    /// control records keep their order, while base address records are
+    /// regenerated as extended linear address records where needed.
+    fn rewrite_data(&mut self, mut f: impl FnMut(u32, Bytes) -> Option<(u32, Bytes)>) {
+        let resolved: BTreeMap<usize, u32> = self.resolved_data_records()
+            .into_iter()
+            .collect();
+
+        let mut records = Vec::<Record>::new();
+        let mut base = Some(0);
+
+        for (i, record) in self.records.drain(..).enumerate() {
+            match resolved.get(&i) {
+                Some(start) => if let Some((addr, data)) = f(*start, record.data) {
+                    records.extend(encode_data(addr, &data, u8::MAX, &mut base));
+                },
+                None => if record.base_address().is_none() {
+                    records.push(record);
+                }
+            }
+        }
+
+        self.records = records;
+    }
+
+    /// Splits every data record that crosses a 64K boundary, inserting the
+    /// extended linear address record its upper part needs. If later data
+    /// records rely on the base that was in effect, that base record is
+    /// repeated after the split. All other records are left as they are,
+    /// and the resolved image is unchanged.
+    pub fn enforce_bank_boundaries(&mut self) {
+        let original = std::mem::take(&mut self.records);
+        let mut base: u32 = 0;
+        let mut base_record: Option<&Record> = None;
+
+        for (i, record) in original.iter().enumerate() {
+            if let Some(address) = record.base_address() {
+                base = address;
+                base_record = Some(record);
+            }
+
+            let start = base.wrapping_add(record.addr as u32);
+            let lower_len = 0x1_0000 - (start & 0xFFFF) as usize;
+
+            if record.rtype != RecordType::Data || record.data.len() <= lower_len {
+                self.records.push(record.clone());
+                continue
+            }
+
+            self.records.push(
+                Record::new(RecordType::Data, record.addr, record.data.slice(..lower_len))
+            );
+            self.records.extend(encode_data(
+                start.wrapping_add(lower_len as u32),
+                &record.data[lower_len..],
+                u8::MAX,
+                &mut None
+            ));
+
+            let base_needed = original[i + 1..]
+                .iter()
+                .take_while(|r| r.base_address().is_none())
+                .any(|r| r.rtype == RecordType::Data);
+
+            if base_needed {
+                self.records.push(match base_record {
+                    Some(r) => r.clone(),
+                    None => Record::new(
+                        RecordType::ExtendedLinearAddress, 0, Bytes::from_static(&[0, 0])
+                    )
+                });
+            }
+        }
+    }
+
+    /// Removes all data below `addr`, trimming any record that straddles it,
+    /// and rebuilds the base address records. Data at and above `addr` is
+    /// unchanged.
+    pub fn strip_below(&mut self, addr: u32) {
+        self.rewrite_data(|start, data| {
+            let end = start as u64 + data.len() as u64;
+
+            if end <= addr as u64 {
+                None
+            } else if start < addr {
+                Some((addr, data.slice((addr - start) as usize..)))
+            } else {
+                Some((start, data))
+            }
+        });
+    }
+
+    /// Index and absolute start address of every data record, applying
+    /// extended segment/linear address records in file order.
+    fn resolved_data_records(&self) -> Vec<(usize, u32)> {
+        let mut resolved = Vec::<(usize, u32)>::new();
+        let mut base: u32 = 0;
+
+        for (i, record) in self.records.iter().enumerate() {
+            match record.rtype {
+                RecordType::Data => {
+                    resolved.push((i, base.wrapping_add(record.addr as u32)))
+                },
+                _ => if let Some(address) = record.base_address() {
+                    base = address
+                }
+            }
+        }
+
+        return resolved;
+    }
+
+    /// Base address in effect just before the record at `index`.
+    fn base_at(&self, index: usize) -> u32 {
+        self.records[..index]
+            .iter()
+            .filter_map(|r| r.base_address())
+            .next_back()
+            .unwrap_or(0)
+    }
+
+    /// Position ahead of the final EOF record, or the end if there is none.
+    fn insertion_index(&self) -> usize {
+        self.records
+            .iter()
+            .rposition(|r| r.rtype == RecordType::EndOfFile)
+            .unwrap_or(self.records.len())
+    }
+
+    fn insert_records(&mut self, records: Vec<Record>) {
+        let index = self.insertion_index();
+        self.records.splice(index..index, records);
+    }
+
+    /// Resolved byte image; later records overwrite earlier ones.
+    fn image(&self) -> BTreeMap<u32, u8> {
+        let mut image = BTreeMap::<u32, u8>::new();
+
+        for (i, start) in self.resolved_data_records() {
+            for (offset, byte) in self.records[i].data.iter().enumerate() {
+                image.insert(start.wrapping_add(offset as u32), *byte);
+            }
+        }
+
+        return image;
+    }
+
+    /// Non-overlapping runs of resolved data as `(start address, bytes)`,
+    /// ordered by address. Each run is a slice of a single record's data,
+    /// so this costs one entry per record rather than one per byte.
+    fn resolved_runs(&self) -> Vec<(u32, Bytes)> {
+        let mut runs = BTreeMap::<u64, (u64, Bytes)>::new();
+
+        let mut insert = |start: u64, data: Bytes| {
+            let end = start + data.len() as u64;
+
+            let overlapping: Vec<u64> = runs
+                .range(..end)
+                .rev()
+                .take_while(|(_, (run_end, _))| *run_end > start)
+                .map(|(run_start, _)| *run_start)
+                .collect();
+
+            for run_start in overlapping {
+                let (run_end, run_data) = runs.remove(&run_start).unwrap();
+
+                if run_start < start {
+                    runs.insert(
+                        run_start,
+                        (start, run_data.slice(..(start - run_start) as usize))
+                    );
+                }
+                if run_end > end {
+                    runs.insert(
+                        end,
+                        (run_end, run_data.slice((end - run_start) as usize..))
+                    );
+                }
+            }
+
+            runs.insert(start, (end, data));
+        };
+
+        for (i, start) in self.resolved_data_records() {
+            let data = self.records[i].data.clone();
+            let start = start as u64;
+
+            if data.is_empty() {
+                continue
+            }
+
+            // Data running past the top of the address space wraps to 0.
+            match (start + data.len() as u64).checked_sub(0x1_0000_0000) {
+                Some(wrapped) if wrapped > 0 => {
+                    let split = data.len() - wrapped as usize;
+                    insert(start, data.slice(..split));
+                    insert(0, data.slice(split..));
+                },
+                _ => insert(start, data)
+            }
+        }
+
+        return runs
+            .into_iter()
+            .map(|(start, (_, data))| (start as u32, data))
+            .collect();
+    }
+
+    /// Populated bytes as `(address, byte)` from lowest to highest address.
+    pub fn bytes(&self) -> impl DoubleEndedIterator<Item = (u32, u8)> {
+        self.resolved_runs()
+            .into_iter()
+            .flat_map(|(start, data)| {
+                (0..data.len()).map(move |i| (start + i as u32, data[i]))
+            })
+    }
+
+    /// Populated bytes as `(address, byte)` from highest to lowest address,
+    /// without materializing the forward sequence.
+    pub fn bytes_rev(&self) -> impl Iterator<Item = (u32, u8)> {
+        self.bytes().rev()
+    }
+
+    /// Contiguous runs of resolved data as `(start address, bytes)`,
+    /// ordered by address. A segment backed by a single record shares that
+    /// record's buffer; data is only copied where adjacent records are
+    /// joined.
+    pub fn segments(&self) -> Vec<(u32, Bytes)> {
+        let mut segments = Vec::<(u32, Bytes)>::new();
+        let mut pieces = Vec::<Bytes>::new();
+        let mut start: u32 = 0;
+        let mut end: u64 = 0;
+
+        let join = |pieces: &mut Vec<Bytes>| -> Bytes {
+            if pieces.len() == 1 {
+                return pieces.pop().unwrap();
+            }
+
+            let mut data = BytesMut::new();
+            for piece in pieces.drain(..) {
+                data.put(piece);
+            }
+
+            return data.freeze();
+        };
+
+        for (addr, data) in self.resolved_runs() {
+            if !pieces.is_empty() && addr as u64 != end {
+                segments.push((start, join(&mut pieces)));
+            }
+
+            if pieces.is_empty() {
+                start = addr;
+            }
+
+            end = addr as u64 + data.len() as u64;
+            pieces.push(data);
+        }
+
+        if !pieces.is_empty() {
+            segments.push((start, join(&mut pieces)));
+        }
+
+        return segments;
+    }
+
+    /// Base64 of the flat binary (see `to_binary`) behind a 12-byte header:
+    /// the magic `IHX1`, then the base address and the length as
+    /// big-endian `u32`s. Gaps between segments are stored as `fill`, so
+    /// they come back as populated bytes, and the start address is dropped.
+    #[cfg(feature = "base64")]
+    pub fn to_base64(&self, fill: u8) -> String {
+        let (base, data) = self.to_binary(fill);
+
+        let mut blob = BytesMut::with_capacity(12 + data.len());
+        blob.put(&BASE64_MAGIC[..]);
+        blob.put_u32(base);
+        blob.put_u32(data.len() as u32);
+        blob.put(data);
+
+        return BASE64.encode(blob);
+    }
+
+    /// Rebuilds an image from the output of `to_base64`.
+    #[cfg(feature = "base64")]
+    pub fn from_base64(encoded: &str) -> Result<Self, IntelHexError> {
+        let blob = match BASE64.decode(encoded.trim()) {
+            Ok(blob) => blob,
+            Err(err) => return Err(IHexError::ImageBadEncoding.new(
+                "Error while decoding base64"
+            ).set_source(Box::new(err)))
+        };
+
+        if blob.len() < 12 || &blob[0..4] != BASE64_MAGIC {
+            return Err(IHexError::ImageBadEncoding.new("Missing base64 image header"))
+        }
+
+        let base = u32::from_be_bytes([blob[4], blob[5], blob[6], blob[7]]);
+        let len = u32::from_be_bytes([blob[8], blob[9], blob[10], blob[11]]) as usize;
+        let data = &blob[12..];
+
+        if data.len() != len {
+            return Err(IHexError::ImageBadEncoding.new(&format!(
+                "Image length: {}, expected: {}", data.len(), len
+            )))
+        }
+
+        return Self::from_binary(base, data);
+    }
+
+    /// Running CRC-32 of the flat binary (see `to_binary`) at the end of
+    /// each flash page. Pages are aligned to absolute multiples of
+    /// `page_size`: the first page is padded with `fill` below the lowest
+    /// populated address, so the CRC starts at that page's boundary. A
+    /// trailing partial page gets its own entry.
+    ///
+    /// Panics if `page_size` is zero.
+    pub fn page_checksums(&self, page_size: usize, fill: u8) -> Vec<u32> {
+        assert!(page_size > 0, "page size must be non-zero");
+
+        let (start, binary) = self.to_binary(fill);
+        let lead = vec![fill; (start as u64 % page_size as u64) as usize];
+
+        let mut checksum = RollingChecksum::new();
+        let mut flat = BytesMut::with_capacity(lead.len() + binary.len());
+        flat.put(&lead[..]);
+        flat.put(binary);
+
+        return flat
+            .chunks(page_size)
+            .map(|page| {
+                checksum.update(page);
+                checksum.value()
+            })
+            .collect();
+    }
+
+    /// `segments` keyed by start address, for range lookups.
+    pub fn segment_map(&self) -> BTreeMap<u32, Bytes> {
+        self.segments().into_iter().collect()
+    }
+
+    /// Lowest and highest populated address, or `(0, 0)` for an empty image.
+    pub fn address_span(&self) -> (u32, u32) {
+        let image = self.image();
+
+        match (image.first_key_value(), image.last_key_value()) {
+            (Some((low, _)), Some((high, _))) => (*low, *high),
+            _ => (0, 0)
+        }
+    }
+
+    /// Number of populated bytes in the resolved image.
+    pub fn data_len(&self) -> usize {
+        self.image().len()
+    }
+
+    /// Compares `data_len` against `max_bytes`, warning once usage exceeds
+    /// `warn_pct` percent of the budget.
+    pub fn check_budget(&self, max_bytes: usize, warn_pct: f64) -> BudgetStatus {
+        let used = self.data_len();
+
+        if used > max_bytes {
+            BudgetStatus::Over { used, max: max_bytes }
+        } else if used as f64 > max_bytes as f64 * warn_pct / 100.0 {
+            BudgetStatus::Warn { used, max: max_bytes }
+        } else {
+            BudgetStatus::Ok
+        }
+    }
+
+    /// Number of populated bytes in `[start, end)` and the fraction of the
+    /// region they occupy. An empty region reports `(0, 0.0)`.
+    pub fn region_usage(&self, start: u32, end: u32) -> (usize, f64) {
+        if end <= start {
+            return (0, 0.0);
+        }
+
+        let used = self.image().range(start..end).count();
+
+        return (used, used as f64 / (end - start) as f64);
+    }
+
+    /// Whether every populated address fits in 16 bits, i.e. the image can
+    /// be flashed to a target without extended addressing.
+    pub fn is_16bit_addressable(&self) -> bool {
+        self.address_span().1 <= 0xFFFF
+    }
+
+    /// Smallest SREC address width in bytes (2 for S1, 3 for S2, 4 for S3)
+    /// that can hold the highest populated address.
+    pub fn srec_address_width(&self) -> u8 {
+        match self.address_span().1 {
+            0..=0xFFFF => 2,
+            0x1_0000..=0xFF_FFFF => 3,
+            _ => 4
+        }
+    }
+
+    /// Entry point from the last start segment/linear address record.
+    pub fn start_address(&self) -> Option<u32> {
+        self.records.iter().rev().find_map(|r| {
+            if r.data.len() != 4 {
+                return None;
+            }
+
+            match r.rtype {
+                RecordType::StartSegmentAddress => {
+                    let cs = u16::from_be_bytes([r.data[0], r.data[1]]) as u32;
+                    let ip = u16::from_be_bytes([r.data[2], r.data[3]]) as u32;
+                    Some((cs << 4) + ip)
+                },
+                RecordType::StartLinearAddress => {
+                    Some(u32::from_be_bytes([r.data[0], r.data[1], r.data[2], r.data[3]]))
+                },
+                _ => None
+            }
+        })
+    }
+
+    /// Whether the entry point is a populated address. Files without a
+    /// start address record are considered consistent.
+    pub fn entry_in_image(&self) -> bool {
+        match self.start_address() {
+            Some(entry) => self.byte_at(entry).is_some(),
+            None => true
+        }
+    }
+
+    /// Resolved byte at `addr`, or `None` if it is unpopulated.
+    pub fn byte_at(&self, addr: u32) -> Option<u8> {
+        self.resolved_data_records()
+            .into_iter()
+            .rev()
+            .find_map(|(i, start)| {
+                let offset = addr.wrapping_sub(start) as usize;
+                self.records[i].data.get(offset).copied()
+            })
+    }
+
+    fn read_bytes<const N: usize>(&self, addr: u32) -> Option<[u8; N]> {
+        let mut bytes = [0u8; N];
+
+        for (i, byte) in bytes.iter_mut().enumerate() {
+            *byte = self.byte_at(addr.checked_add(i as u32)?)?;
+        }
+
+        return Some(bytes);
+    }
+
+    /// Reads a 16-bit value at `addr`, or `None` if any byte is unpopulated.
+    pub fn read_u16(&self, addr: u32, endian: Endian) -> Option<u16> {
+        let bytes = self.read_bytes::<2>(addr)?;
+
+        match endian {
+            Endian::Little => Some(u16::from_le_bytes(bytes)),
+            Endian::Big => Some(u16::from_be_bytes(bytes))
+        }
+    }
+
+    /// Reads a 32-bit value at `addr`, or `None` if any byte is unpopulated.
+    pub fn read_u32(&self, addr: u32, endian: Endian) -> Option<u32> {
+        let bytes = self.read_bytes::<4>(addr)?;
+
+        match endian {
+            Endian::Little => Some(u32::from_le_bytes(bytes)),
+            Endian::Big => Some(u32::from_be_bytes(bytes))
+        }
+    }
+
+    /// Overwrites already populated bytes starting at `addr`, updating every
+    /// data record that holds them.
+    fn write_bytes(&mut self, addr: u32, bytes: &[u8]) -> Result<(), IntelHexError> {
+        for i in 0..bytes.len() {
+            let populated = addr
+                .checked_add(i as u32)
+                .and_then(|a| self.byte_at(a))
+                .is_some();
+
+            if !populated {
+                return Err(IHexError::ImageUnpopulated.new(&format!(
+                    "Address 0x{:X} is not populated",
+                    addr as u64 + i as u64
+                )))
+            }
+        }
+
+        for (i, start) in self.resolved_data_records() {
+            let record = &mut self.records[i];
+            let mut data = BytesMut::from(&record.data[..]);
+            let mut changed = false;
+
+            for (offset, byte) in bytes.iter().enumerate() {
+                let index = (addr + offset as u32).wrapping_sub(start) as usize;
+                if index < data.len() {
+                    data[index] = *byte;
+                    changed = true;
+                }
+            }
+
+            if changed {
+                record.data = data.freeze();
+                record.update_checksum();
+            }
+        }
+
+        return Ok(());
+    }
+
+    /// Writes a 16-bit value at `addr`. All target bytes must already be
+    /// populated.
+    pub fn write_u16(&mut self, addr: u32, value: u16, endian: Endian) -> Result<(), IntelHexError> {
+        match endian {
+            Endian::Little => self.write_bytes(addr, &value.to_le_bytes()),
+            Endian::Big => self.write_bytes(addr, &value.to_be_bytes())
+        }
+    }
+
+    /// Writes a 32-bit value at `addr`. All target bytes must already be
+    /// populated.
+    pub fn write_u32(&mut self, addr: u32, value: u32, endian: Endian) -> Result<(), IntelHexError> {
+        match endian {
+            Endian::Little => self.write_bytes(addr, &value.to_le_bytes()),
+            Endian::Big => self.write_bytes(addr, &value.to_be_bytes())
+        }
+    }
+
+    /// Flat binary covering the lowest to the highest populated address,
+    /// with gaps set to `fill`. Returns the start address and the data.
+    pub fn to_binary(&self, fill: u8) -> (u32, Bytes) {
+        let image = self.image();
+
+        let (start, end) = match (image.first_key_value(), image.last_key_value()) {
+            (Some((start, _)), Some((end, _))) => (*start, *end),
+            _ => return (0, Bytes::new())
+        };
+
+        let mut binary = vec![fill; (end - start) as usize + 1];
+
+        for (addr, byte) in image {
+            binary[(addr - start) as usize] = byte;
+        }
+
+        return (start, Bytes::from(binary));
+    }
+
+    /// Unpopulated ranges `[start, stop)` of `segments` within `[from, to)`.
+    fn gaps(segments: &[(u32, Bytes)], from: u64, to: u64) -> Vec<(u64, u64)> {
+        let mut gaps = Vec::<(u64, u64)>::new();
+        let mut cursor = from;
+
+        for (start, data) in segments {
+            let start = (*start as u64).clamp(from, to);
+            if start > cursor {
+                gaps.push((cursor, start));
+            }
+            cursor = cursor.max((start + data.len() as u64).min(to));
+        }
+
+        if to > cursor {
+            gaps.push((cursor, to));
+        }
+
+        return gaps;
+    }
+
+    /// Records that would fill every gap between the lowest and highest
+    /// populated address with `pad`, without modifying the file. The run for
+    /// each gap starts with its own extended linear address record, so any
+    /// run can be inserted on its own, anywhere in a file.
+    ///
+    /// Panics if `record_len` is zero.
+    pub fn gap_fill_records(&self, pad: u8, record_len: u8) -> Vec<Record> {
+        let segments = self.segments();
+
+        let (from, to) = match (segments.first(), segments.last()) {
+            (Some((start, _)), Some((last, data))) => {
+                (*start as u64, *last as u64 + data.len() as u64)
+            },
+            _ => return Vec::new()
+        };
+
+        let mut records = Vec::<Record>::new();
+
+        for (start, stop) in Self::gaps(&segments, from, to) {
+            records.extend(encode_data(
+                start as u32,
+                &vec![pad; (stop - start) as usize],
+                record_len,
+                &mut None
+            ));
+        }
+
+        return records;
+    }
+
+    /// Records that would overwrite `[addr, addr + data.len())` with `data`,
+    /// without modifying the file. They start with an extended linear
+    /// address record, so they can be appended to any file or delta. Fails
+    /// if the patch would run past the 32-bit address space.
+    ///
+    /// Panics if `record_len` is zero.
+    pub fn patch_records(
+        &self, addr: u32, data: &[u8], record_len: u8
+    ) -> Result<Vec<Record>, IntelHexError> {
+        match check_address_range(addr, data.len()) {
+            Ok(_) => Ok(encode_data(addr, data, record_len, &mut None)),
+            Err(err) => Err(err)
+        }
+    }
+
+    /// Fills the region `[base, base + size)` so the image covers it
+    /// exactly, writing `fill` into every unpopulated address. Fails if
+    /// any existing data lies outside the region.
+    pub fn pad_to(&mut self, base: u32, size: u32, fill: u8) -> Result<(), IntelHexError> {
+        let end = base as u64 + size as u64;
+
+        if end > 0x1_0000_0000 {
+            return Err(IHexError::ImageOutOfRange.new(&format!(
+                "Region 0x{:X} + 0x{:X} exceeds the 32-bit address space",
+                base, size
+            )))
+        }
+
+        let segments = self.segments();
+
+        for (start, data) in &segments {
+            let seg_end = *start as u64 + data.len() as u64;
+            if (*start as u64) < base as u64 || seg_end > end {
+                return Err(IHexError::ImageOutOfRange.new(&format!(
+                    "Data at 0x{:X}..0x{:X} lies outside region 0x{:X}..0x{:X}",
+                    start, seg_end, base, end
+                )))
+            }
+        }
+
+        let mut current_base = Some(self.base_at(self.insertion_index()));
+        let mut records = Vec::<Record>::new();
+
+        for (start, stop) in Self::gaps(&segments, base as u64, end) {
+            records.extend(encode_data(
+                start as u32,
+                &vec![fill; (stop - start) as usize],
+                DEFAULT_RECORD_LEN,
+                &mut current_base
+            ));
+        }
+
+        self.insert_records(records);
+
+        return Ok(());
+    }
+
+    /// Verilog `$readmemh` text with `width_bytes`-wide words. Address
+    /// directives are word addresses (byte address / width); partially
+    /// populated words are completed with `fill`. Bytes are packed
+    /// little-endian, so the lowest address is the least significant byte.
+    ///
+    /// Panics if `width_bytes` is zero.
+    pub fn to_readmemh(&self, width_bytes: usize, fill: u8) -> String {
+        assert!(width_bytes > 0, "readmemh word width must be non-zero");
+
+        let width = width_bytes as u64;
+        let mut words = BTreeMap::<u64, Vec<u8>>::new();
+
+        for (addr, byte) in self.image() {
+            let addr = addr as u64;
+            let word = words
+                .entry(addr / width)
+                .or_insert_with(|| vec![fill; width_bytes]);
+            word[(addr % width) as usize] = byte;
+        }
+
+        let mut hex_str = String::new();
+        let mut next: Option<u64> = None;
+
+        for (word_addr, word) in words {
+            if next != Some(word_addr) {
+                hex_str += &format!("@{:08X}\n", word_addr);
+            }
+
+            for byte in word.iter().rev() {
+                hex_str += &format!("{:02X}", byte);
+            }
+            hex_str += "\n";
+
+            next = Some(word_addr + 1);
+        }
+
+        return hex_str;
+    }
+
+    /// Indices of control records that have no effect: EOF records other
+    /// than the last, start address records repeating an earlier one, and
+    /// base address records that either leave the base unchanged or are
+    /// superseded before any data record uses them.
+    pub fn redundant_control_records(&self) -> Vec<usize> {
+        let mut redundant = Vec::<usize>::new();
+
+        let last_eof = self.records
+            .iter()
+            .rposition(|r| r.rtype == RecordType::EndOfFile);
+
+        let mut starts = Vec::<&Record>::new();
+        let mut committed_base: u32 = 0;
+        let mut base: u32 = 0;
+        let mut pending: Option<usize> = None;
+
+        for (i, record) in self.records.iter().enumerate() {
+            match record.rtype {
+                RecordType::Data => {
+                    committed_base = base;
+                    pending = None;
+                },
+                RecordType::EndOfFile => if Some(i) != last_eof {
+                    redundant.push(i)
+                },
+                RecordType::StartSegmentAddress | RecordType::StartLinearAddress => {
+                    if starts.iter().any(
+                        |s| s.rtype == record.rtype && s.data == record.data
+                    ) {
+                        redundant.push(i);
+                    } else {
+                        starts.push(record);
+                    }
+                },
+                RecordType::ExtendedSegmentAddress | RecordType::ExtendedLinearAddress => {
+                    let Some(address) = record.base_address() else {
+                        continue
+                    };
+
+                    if let Some(p) = pending.take() {
+                        redundant.push(p);
+                    }
+
+                    if address == committed_base {
+                        redundant.push(i);
+                    } else {
+                        pending = Some(i);
+                    }
+
+                    base = address;
+                }
+            }
+        }
+
+        if let Some(p) = pending {
+            redundant.push(p);
+        }
+
+        redundant.sort_unstable();
+
+        return redundant;
+    }
+
     pub fn save_file(&mut self, path: &str) -> Result<(), IntelHexError> {
         match write(path, self.to_hex_str()) {
             Ok(_) => Ok(()),
@@ -1,5 +1,8 @@
+#![allow(clippy::needless_return, clippy::question_mark, clippy::new_ret_no_self)]
+
 pub mod util;
+pub mod checksum;
 pub mod error;
 pub mod file;
 
-pub use file::IntelHexFile;
+pub use file::{IntelHexFile, AddressingMode, BudgetStatus, CanonicalizeReport, Endian, ParseOptions};
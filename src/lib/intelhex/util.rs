@@ -26,5 +26,5 @@ pub fn display_file_info(file: &IntelHexFile, mut n: usize) {
 }
 
 pub fn twos_comp(input: u64) -> u8 {
-    return (input as i64 * -1i64) as u8;
+    return -(input as i64) as u8;
 }
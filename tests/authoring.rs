@@ -0,0 +1,93 @@
+use bytes::Bytes;
+
+use intelhex::{AddressingMode, IntelHexFile};
+use intelhex::file::RecordType;
+
+fn layout(file: &IntelHexFile) -> Vec<(RecordType, u16, Vec<u8>)> {
+    file.records
+        .iter()
+        .map(|r| match r.rtype {
+            RecordType::Data => (r.rtype, r.addr, vec![r.len]),
+            _ => (r.rtype, r.addr, r.data.to_vec())
+        })
+        .collect()
+}
+
+fn assert_reloads_to(file: &IntelHexFile, addr: u32, data: &[u8]) {
+    let reloaded = IntelHexFile::load(&file.to_hex_str()).unwrap();
+    assert_eq!(reloaded.segments(), vec![(addr, Bytes::copy_from_slice(data))]);
+}
+
+// Data records are listed as (type, offset, [length]); base records as
+// (type, 0, segment bytes).
+
+#[test]
+fn segment_mode_unaligned_address() {
+    let data = [1, 2, 3, 4];
+    let file = IntelHexFile::from_binary_with_mode(
+        0x12345, &data, AddressingMode::Segment
+    ).unwrap();
+
+    assert_eq!(layout(&file), vec![
+        (RecordType::ExtendedSegmentAddress, 0, vec![0x12, 0x34]),
+        (RecordType::Data, 0x0005, vec![4]),
+        (RecordType::EndOfFile, 0, vec![])
+    ]);
+    assert_reloads_to(&file, 0x12345, &data);
+}
+
+#[test]
+fn segment_mode_run_crossing_segment_reach() {
+    let data: Vec<u8> = (0..16).collect();
+    let file = IntelHexFile::from_binary_with_mode(
+        0xFFF8, &data, AddressingMode::Segment
+    ).unwrap();
+
+    assert_eq!(layout(&file), vec![
+        (RecordType::Data, 0xFFF8, vec![8]),
+        (RecordType::ExtendedSegmentAddress, 0, vec![0x10, 0x00]),
+        (RecordType::Data, 0x0000, vec![8]),
+        (RecordType::EndOfFile, 0, vec![])
+    ]);
+    assert_reloads_to(&file, 0xFFF8, &data);
+}
+
+#[test]
+fn segment_mode_top_segment() {
+    let data = [0xAA; 16];
+    let file = IntelHexFile::from_binary_with_mode(
+        0x10_FFE0, &data, AddressingMode::Segment
+    ).unwrap();
+
+    assert_eq!(layout(&file), vec![
+        (RecordType::ExtendedSegmentAddress, 0, vec![0xFF, 0xFF]),
+        (RecordType::Data, 0xFFF0, vec![16]),
+        (RecordType::EndOfFile, 0, vec![])
+    ]);
+    assert_reloads_to(&file, 0x10_FFE0, &data);
+}
+
+#[test]
+fn segment_mode_rejects_addresses_above_ceiling() {
+    let result = IntelHexFile::from_binary_with_mode(
+        0x10_FFF0, &[0xAA; 16], AddressingMode::Segment
+    );
+
+    assert!(format!("{:?}", result.err().unwrap()).contains("ImageOutOfRange"));
+}
+
+#[test]
+fn push_data_linear_after_segment_base() {
+    let mut file = IntelHexFile::from_binary_with_mode(
+        0x12345, &[1, 2], AddressingMode::Segment
+    ).unwrap();
+
+    file.push_data(0x0002_0000, &[3, 4], AddressingMode::Linear).unwrap();
+
+    let reloaded = IntelHexFile::load(&file.to_hex_str()).unwrap();
+    assert_eq!(reloaded.segments(), vec![
+        (0x12345, Bytes::from_static(&[1, 2])),
+        (0x0002_0000, Bytes::from_static(&[3, 4]))
+    ]);
+    assert_eq!(file.records.last().unwrap().rtype, RecordType::EndOfFile);
+}
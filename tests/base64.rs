@@ -0,0 +1,69 @@
+#![cfg(feature = "base64")]
+
+use base64::{Engine, engine::general_purpose::STANDARD as BASE64};
+
+use bytes::Bytes;
+
+use intelhex::{AddressingMode, IntelHexFile};
+use intelhex::file::{Record, RecordType};
+
+#[test]
+fn base64_round_trip_preserves_content() {
+    let file = IntelHexFile::load_file("examples/example.hex").unwrap();
+
+    let decoded = IntelHexFile::from_base64(&file.to_base64(0xFF)).unwrap();
+
+    for (addr, byte) in file.bytes() {
+        assert_eq!(decoded.byte_at(addr), Some(byte));
+    }
+    assert_eq!(decoded.address_span(), file.address_span());
+    assert_eq!(decoded.segments(), vec![file.to_binary(0xFF)]);
+}
+
+#[test]
+fn base64_round_trip_fills_gaps_and_drops_start_address() {
+    let mut file = IntelHexFile::from_binary(0x100, &[1, 2]).unwrap();
+    file.push_data(0x104, &[3], AddressingMode::Linear).unwrap();
+    file.records.insert(file.records.len() - 1, Record::new(
+        RecordType::StartLinearAddress, 0, Bytes::from_static(&[0, 0, 1, 0])
+    ));
+
+    let decoded = IntelHexFile::from_base64(&file.to_base64(0xEE)).unwrap();
+
+    assert_eq!(file.segments().len(), 2);
+    assert_eq!(decoded.segments(), vec![
+        (0x100, Bytes::from_static(&[1, 2, 0xEE, 0xEE, 3]))
+    ]);
+    assert_eq!(file.start_address(), Some(0x100));
+    assert_eq!(decoded.start_address(), None);
+}
+
+#[test]
+fn base64_header_layout() {
+    let file = IntelHexFile::from_binary(0x0001_0100, &[1, 2, 3]).unwrap();
+
+    let blob = BASE64.decode(file.to_base64(0xFF)).unwrap();
+
+    assert_eq!(&blob[0..4], b"IHX1");
+    assert_eq!(&blob[4..8], &[0x00, 0x01, 0x01, 0x00]);
+    assert_eq!(&blob[8..12], &[0x00, 0x00, 0x00, 0x03]);
+    assert_eq!(&blob[12..], &[1, 2, 3]);
+}
+
+#[test]
+fn base64_rejects_malformed_input() {
+    let blob = |magic: &[u8], base: u32, len: u32, data: &[u8]| {
+        let mut blob = magic.to_vec();
+        blob.extend(base.to_be_bytes());
+        blob.extend(len.to_be_bytes());
+        blob.extend(data);
+        BASE64.encode(blob)
+    };
+
+    assert!(IntelHexFile::from_base64("not base64!").is_err());
+    assert!(IntelHexFile::from_base64(&BASE64.encode(b"IHX1")).is_err());
+    assert!(IntelHexFile::from_base64(&blob(b"XXXX", 0, 1, &[1])).is_err());
+    assert!(IntelHexFile::from_base64(&blob(b"IHX1", 0, 2, &[1])).is_err());
+    assert!(IntelHexFile::from_base64(&blob(b"IHX1", 0xFFFF_FFFE, 4, &[1, 2, 3, 4])).is_err());
+    assert!(IntelHexFile::from_base64(&blob(b"IHX1", 0xFFFF_FFFC, 4, &[1, 2, 3, 4])).is_ok());
+}
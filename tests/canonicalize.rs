@@ -0,0 +1,115 @@
+mod common;
+
+use common::{data, ela, eof, esa, file, filled, start_linear, start_segment};
+
+use intelhex::IntelHexFile;
+
+#[test]
+fn canonicalize_report_merges_exact_sources() {
+    let mut file = file(&[
+        filled(0x00, 0xA0, 8),
+        filled(0x64, 0xA1, 8),
+        filled(0x08, 0xA2, 8),
+        eof()
+    ]);
+
+    let report = file.canonicalize_with_report();
+
+    assert_eq!(report.merged, vec![vec![0, 2]]);
+    assert!(report.removed.is_empty());
+    assert!(report.split.is_empty());
+    assert_eq!(report.base_records_added, 0);
+}
+
+#[test]
+fn canonicalize_report_removed_records() {
+    let mut file = file(&[
+        ela(0x0000),
+        filled(0x00, 0xA0, 16),
+        filled(0x00, 0xA1, 16),
+        eof(),
+        eof()
+    ]);
+    let segments = file.segments();
+
+    let report = file.canonicalize_with_report();
+
+    assert_eq!(report.removed, vec![0, 1, 3]);
+    assert!(report.merged.is_empty());
+    assert!(report.split.is_empty());
+    assert_eq!(report.base_records_added, 0);
+    assert_eq!(file.segments(), segments);
+}
+
+#[test]
+fn canonicalize_report_split_and_base_records_added() {
+    let mut file = file(&[filled(0xFFF8, 0xA0, 16), eof()]);
+
+    let report = file.canonicalize_with_report();
+
+    assert_eq!(report.split, vec![0]);
+    assert_eq!(report.base_records_added, 1);
+    assert!(report.removed.is_empty());
+    assert!(report.merged.is_empty());
+    assert!(!report.is_unchanged());
+}
+
+#[test]
+fn canonicalize_report_unchanged() {
+    let mut file = IntelHexFile::load_file("examples/example.hex").unwrap();
+
+    assert!(file.canonicalize_with_report().is_unchanged());
+}
+
+#[test]
+fn canonicalize_report_replaced_segment_base() {
+    let mut file = file(&[esa(0x1000), filled(0x00, 0xA0, 16), eof()]);
+
+    let report = file.canonicalize_with_report();
+
+    assert_eq!(report.removed, vec![0]);
+    assert_eq!(report.base_records_added, 1);
+    assert!(!report.is_unchanged());
+    assert_eq!(file.segments(), vec![(0x1_0000, vec![0xA0; 16].into())]);
+}
+
+#[test]
+fn fingerprint_ignores_record_layout() {
+    let bytes: Vec<u8> = (0..32).collect();
+
+    let single = file(&[ela(0x0001), data(0x0000, &bytes), eof()]);
+    let split = file(&[
+        ela(0x0001),
+        data(0x0010, &bytes[16..]),
+        esa(0x1000),
+        filled(0x0000, 0xEE, 4),
+        data(0x0000, &bytes[..16]),
+        eof(),
+        eof()
+    ]);
+    let shadowed = file(&[ela(0x0001), filled(0x0000, 0xEE, 32), data(0x0000, &bytes), eof()]);
+
+    assert_eq!(split.fingerprint(), single.fingerprint());
+    assert_eq!(shadowed.fingerprint(), single.fingerprint());
+    assert_eq!(single.fingerprint().len(), 16);
+}
+
+#[test]
+fn fingerprint_tracks_content_and_entry_point() {
+    let bytes: Vec<u8> = (0..32).collect();
+    let mut changed = bytes.clone();
+    changed[31] ^= 0xFF;
+
+    let base = file(&[data(0x0100, &bytes), eof()]);
+
+    assert_ne!(file(&[data(0x0100, &changed), eof()]).fingerprint(), base.fingerprint());
+    assert_ne!(file(&[data(0x0101, &bytes), eof()]).fingerprint(), base.fingerprint());
+
+    let linear = file(&[data(0x0100, &bytes), start_linear(0x0104), eof()]);
+    let segment = file(&[data(0x0100, &bytes), start_segment(0x0010, 0x0004), eof()]);
+    let other = file(&[data(0x0100, &bytes), start_linear(0x0108), eof()]);
+
+    assert_eq!(segment.fingerprint(), linear.fingerprint());
+    assert_ne!(other.fingerprint(), linear.fingerprint());
+    assert_ne!(linear.fingerprint(), base.fingerprint());
+}
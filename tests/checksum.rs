@@ -0,0 +1,46 @@
+use intelhex::IntelHexFile;
+use intelhex::checksum::{crc32, RollingChecksum};
+
+#[test]
+fn crc32_check_value() {
+    assert_eq!(crc32(b"123456789"), 0xCBF4_3926);
+    assert_eq!(crc32(b""), 0);
+}
+
+#[test]
+fn rolling_checksum_matches_one_shot() {
+    let mut checksum = RollingChecksum::new();
+    assert!(checksum.is_empty());
+
+    checksum.update(b"1234");
+    checksum.update(b"");
+    checksum.update(b"56789");
+
+    assert_eq!(checksum.value(), crc32(b"123456789"));
+    assert_eq!(checksum.len(), 9);
+
+    checksum.reset();
+    assert_eq!(checksum, RollingChecksum::default());
+}
+
+#[test]
+fn page_checksums_include_trailing_partial_page() {
+    let data: Vec<u8> = (0..10).collect();
+    let file = IntelHexFile::from_binary(0x100, &data).unwrap();
+
+    let pages = file.page_checksums(4, 0xFF);
+
+    assert_eq!(pages, vec![crc32(&data[..4]), crc32(&data[..8]), crc32(&data)]);
+}
+
+#[test]
+fn page_checksums_align_to_absolute_pages() {
+    let data: Vec<u8> = (0..10).collect();
+    let file = IntelHexFile::from_binary(0x102, &data).unwrap();
+
+    let pages = file.page_checksums(4, 0xFF);
+
+    let mut flat = vec![0xFF, 0xFF];
+    flat.extend(&data);
+    assert_eq!(pages, vec![crc32(&flat[..4]), crc32(&flat[..8]), crc32(&flat)]);
+}
@@ -0,0 +1,48 @@
+#![allow(dead_code)]
+
+use bytes::Bytes;
+
+use intelhex::IntelHexFile;
+use intelhex::file::{Record, RecordType};
+
+pub fn data(addr: u16, bytes: &[u8]) -> Record {
+    Record::new(RecordType::Data, addr, Bytes::copy_from_slice(bytes))
+}
+
+pub fn filled(addr: u16, byte: u8, len: usize) -> Record {
+    Record::new(RecordType::Data, addr, Bytes::from(vec![byte; len]))
+}
+
+pub fn ela(upper: u16) -> Record {
+    Record::new(
+        RecordType::ExtendedLinearAddress, 0, Bytes::copy_from_slice(&upper.to_be_bytes())
+    )
+}
+
+pub fn esa(segment: u16) -> Record {
+    Record::new(
+        RecordType::ExtendedSegmentAddress, 0, Bytes::copy_from_slice(&segment.to_be_bytes())
+    )
+}
+
+pub fn start_linear(entry: u32) -> Record {
+    Record::new(
+        RecordType::StartLinearAddress, 0, Bytes::copy_from_slice(&entry.to_be_bytes())
+    )
+}
+
+pub fn start_segment(cs: u16, ip: u16) -> Record {
+    let mut bytes = cs.to_be_bytes().to_vec();
+    bytes.extend(ip.to_be_bytes());
+    Record::new(RecordType::StartSegmentAddress, 0, Bytes::from(bytes))
+}
+
+pub fn eof() -> Record {
+    Record::new(RecordType::EndOfFile, 0, Bytes::new())
+}
+
+/// Loads a file from the hex text of `records`.
+pub fn file(records: &[Record]) -> IntelHexFile {
+    let lines: Vec<String> = records.iter().map(|r| r.to_hex_str()).collect();
+    IntelHexFile::load(&lines.join("\n")).unwrap()
+}
@@ -0,0 +1,154 @@
+mod common;
+
+use common::{data, ela, eof, file, filled, start_linear, start_segment};
+
+use intelhex::{BudgetStatus, IntelHexFile};
+use intelhex::file::Record;
+
+#[test]
+fn bytes_rev_yields_populated_bytes_top_down() {
+    let file = file(&[
+        data(0x10, &[1, 2, 3, 4]),
+        data(0x40, &[5, 6]),
+        data(0x12, &[7, 8, 9]),
+        eof()
+    ]);
+
+    let reversed: Vec<(u32, u8)> = file.bytes_rev().collect();
+
+    assert_eq!(reversed, vec![
+        (0x41, 6), (0x40, 5), (0x14, 9), (0x13, 8), (0x12, 7), (0x11, 2), (0x10, 1)
+    ]);
+
+    let mut forward: Vec<(u32, u8)> = file.bytes().collect();
+    forward.reverse();
+    assert_eq!(forward, reversed);
+}
+
+#[test]
+fn bytes_ignore_zero_length_records() {
+    let file = file(&[
+        data(0x10, &[1, 2, 3, 4]),
+        data(0x12, &[]),
+        data(0x10, &[]),
+        eof()
+    ]);
+
+    let forward: Vec<(u32, u8)> = file.bytes().collect();
+
+    assert_eq!(forward, vec![(0x10, 1), (0x11, 2), (0x12, 3), (0x13, 4)]);
+    assert_eq!(file.bytes_rev().count(), file.data_len());
+    assert_eq!(file.byte_at(0x12), Some(3));
+}
+
+#[test]
+fn bytes_match_byte_at() {
+    let file = IntelHexFile::load_file("examples/example.hex").unwrap();
+
+    for (addr, byte) in file.bytes_rev() {
+        assert_eq!(file.byte_at(addr), Some(byte));
+    }
+    assert_eq!(file.bytes().count(), file.data_len());
+}
+
+#[test]
+fn readmemh_word_addresses_fill_and_byte_order() {
+    let file = file(&[
+        data(0x101, &[1, 2, 3, 4, 5, 6]),
+        data(0x200, &[0xAA]),
+        eof()
+    ]);
+
+    assert_eq!(
+        file.to_readmemh(4, 0xEE),
+        "@00000040\n030201EE\nEE060504\n@00000080\nEEEEEEAA\n"
+    );
+    assert_eq!(
+        file.to_readmemh(1, 0xEE),
+        "@00000101\n01\n02\n03\n04\n05\n06\n@00000200\nAA\n"
+    );
+}
+
+#[test]
+fn segment_map_joins_adjacent_records() {
+    let file = file(&[
+        data(0x10, &[1, 2, 3, 4]),
+        data(0x40, &[5, 6]),
+        data(0x42, &[7]),
+        data(0x11, &[9]),
+        eof()
+    ]);
+
+    let map = file.segment_map();
+
+    assert_eq!(map.keys().copied().collect::<Vec<u32>>(), vec![0x10, 0x40]);
+    assert_eq!(&map[&0x10][..], &[1, 9, 3, 4]);
+    assert_eq!(&map[&0x40][..], &[5, 6, 7]);
+}
+
+#[test]
+fn segment_map_shares_record_buffers() {
+    let file = file(&[data(0x10, &[1, 2, 3, 4]), data(0x40, &[5, 6]), eof()]);
+
+    let map = file.segment_map();
+
+    assert_eq!(map[&0x10].as_ptr(), file.records[0].data.as_ptr());
+    assert_eq!(map[&0x40].as_ptr(), file.records[1].data.as_ptr());
+}
+
+#[test]
+fn srec_address_width_at_boundaries() {
+    let width = |upper: u16, addr: u16| {
+        file(&[ela(upper), data(addr, &[1]), eof()]).srec_address_width()
+    };
+
+    assert_eq!(width(0x0000, 0xFFFF), 2);
+    assert_eq!(width(0x0001, 0x0000), 3);
+    assert_eq!(width(0x00FF, 0xFFFF), 3);
+    assert_eq!(width(0x0100, 0x0000), 4);
+}
+
+#[test]
+fn region_usage_counts_partial_overlap() {
+    let file = file(&[data(0x10, &[1, 2, 3, 4]), data(0x20, &[5, 6]), eof()]);
+
+    assert_eq!(file.region_usage(0x12, 0x22), (4, 0.25));
+    assert_eq!(file.region_usage(0x00, 0x11), (1, 1.0 / 17.0));
+    assert_eq!(file.region_usage(0x14, 0x20), (0, 0.0));
+    assert_eq!(file.region_usage(0x20, 0x20), (0, 0.0));
+    assert_eq!(file.region_usage(0x22, 0x10), (0, 0.0));
+}
+
+#[test]
+fn is_16bit_addressable_at_boundary() {
+    assert!(file(&[data(0xFFFF, &[1]), eof()]).is_16bit_addressable());
+    assert!(!file(&[data(0xFFFF, &[1, 2]), eof()]).is_16bit_addressable());
+    assert!(!file(&[ela(0x0001), data(0x0000, &[1]), eof()]).is_16bit_addressable());
+}
+
+#[test]
+fn check_budget_thresholds() {
+    let file = file(&[filled(0x00, 0xA0, 80), eof()]);
+
+    assert_eq!(file.check_budget(100, 80.0), BudgetStatus::Ok);
+    assert_eq!(file.check_budget(100, 79.0), BudgetStatus::Warn { used: 80, max: 100 });
+    assert_eq!(file.check_budget(80, 90.0), BudgetStatus::Warn { used: 80, max: 80 });
+    assert_eq!(file.check_budget(80, 100.0), BudgetStatus::Ok);
+    assert_eq!(file.check_budget(79, 90.0), BudgetStatus::Over { used: 80, max: 79 });
+}
+
+#[test]
+fn entry_in_image_resolves_start_records() {
+    let image = [ela(0x0001), data(0x0100, &[1, 2, 3, 4])];
+    let with = |start: Record| {
+        let mut records = image.to_vec();
+        records.extend([start, eof()]);
+        file(&records)
+    };
+
+    assert!(with(start_linear(0x0001_0103)).entry_in_image());
+    assert!(!with(start_linear(0x0001_0104)).entry_in_image());
+    assert!(with(start_segment(0x1000, 0x0100)).entry_in_image());
+    assert!(!with(start_segment(0x1000, 0x0104)).entry_in_image());
+    assert!(file(&[ela(0x0001), data(0x0100, &[1, 2, 3, 4]), eof()]).entry_in_image());
+}
@@ -0,0 +1,67 @@
+use std::env::temp_dir;
+use std::fs::{remove_file, write};
+
+use intelhex::{IntelHexFile, ParseOptions};
+
+const RECORD: &str = ":0B00A00080FA92006F3600C3A00076CB";
+const EOF: &str = ":00000001FF";
+
+fn with_file<T>(name: &str, contents: &[u8], f: impl FnOnce(&str) -> T) -> T {
+    let path = temp_dir().join(format!("intelhex-{}-{}.hex", name, std::process::id()));
+    let path_str = path.to_str().unwrap().to_string();
+
+    write(&path, contents).unwrap();
+    let result = f(&path_str);
+    remove_file(&path).unwrap();
+
+    result
+}
+
+fn limit(max: usize) -> ParseOptions {
+    ParseOptions { max_line_len: Some(max) }
+}
+
+#[test]
+fn max_line_len_accepts_line_at_limit_with_crlf() {
+    let contents = format!("{}\r\n{}\r\n", RECORD, EOF);
+
+    let file = with_file("at-limit", contents.as_bytes(), |path| {
+        IntelHexFile::load_file_with_options(path, &limit(RECORD.len()))
+    }).unwrap();
+    assert_eq!(file.records.len(), 2);
+
+    assert!(IntelHexFile::load_with_options(&contents, &limit(RECORD.len())).is_ok());
+}
+
+#[test]
+fn max_line_len_rejects_line_over_limit() {
+    let contents = format!("{}\r\n{}\r\n{}\r\n", EOF, RECORD, EOF);
+    let options = limit(RECORD.len() - 1);
+
+    let err = with_file("over-limit", contents.as_bytes(), |path| {
+        IntelHexFile::load_file_with_options(path, &options)
+    }).err().unwrap();
+    assert!(format!("{:?}", err).contains("Line 2 exceeds maximum length"));
+
+    let err = IntelHexFile::load_with_options(&contents, &options).err().unwrap();
+    assert!(format!("{:?}", err).contains("Line 2 exceeds maximum length"));
+}
+
+#[test]
+fn max_line_len_rejects_long_line_without_newline() {
+    let contents = vec![b'0'; 4 * 1024 * 1024];
+
+    let err = with_file("no-newline", &contents, |path| {
+        IntelHexFile::load_file_with_options(path, &limit(64))
+    }).err().unwrap();
+    assert!(format!("{:?}", err).contains("Line 1 exceeds maximum length of 64 bytes"));
+}
+
+// `/dev/zero` never ends and never yields a newline, so this only returns if
+// the reader stops at the limit instead of buffering the whole line.
+#[cfg(target_os = "linux")]
+#[test]
+fn max_line_len_stops_reading_unbounded_line() {
+    let err = IntelHexFile::load_file_with_options("/dev/zero", &limit(1024)).err().unwrap();
+    assert!(format!("{:?}", err).contains("Line 1 exceeds maximum length"));
+}
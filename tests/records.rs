@@ -0,0 +1,287 @@
+mod common;
+
+use bytes::Bytes;
+
+use common::{data, ela, eof, esa, file, filled, start_linear};
+
+use intelhex::IntelHexFile;
+use intelhex::file::{Record, RecordType};
+
+#[test]
+fn for_each_record_mut_updates_checksums() {
+    let mut file = IntelHexFile::load_file("examples/example.hex").unwrap();
+
+    file.for_each_record_mut(|record| {
+        if record.rtype == RecordType::Data {
+            record.addr += 0x100;
+        }
+    });
+
+    assert_eq!(file.records[0].addr, 0x180);
+
+    for record in &file.records {
+        assert_eq!(record.checksum, record.calculate_checksum());
+    }
+
+    let reloaded = IntelHexFile::load(&file.to_hex_str()).unwrap();
+    assert_eq!(reloaded.segments(), file.segments());
+}
+
+#[test]
+fn for_each_record_mut_updates_changed_data() {
+    let mut file = IntelHexFile::load_file("examples/example.hex").unwrap();
+
+    file.for_each_record_mut(|record| {
+        if record.rtype == RecordType::Data && record.addr == 0x80 {
+            record.data = Bytes::from_static(&[0xDE, 0xAD, 0xBE, 0xEF]);
+        }
+    });
+
+    assert_eq!(file.records[0].len, 4);
+    assert_eq!(file.records[0].checksum, file.records[0].calculate_checksum());
+
+    let reloaded = IntelHexFile::load(&file.to_hex_str()).unwrap();
+    assert_eq!(reloaded.byte_at(0x80), Some(0xDE));
+    assert_eq!(reloaded.byte_at(0x84), None);
+}
+
+#[test]
+#[should_panic(expected = "record data exceeds 255 bytes")]
+fn for_each_record_mut_rejects_oversized_data() {
+    let mut file = IntelHexFile::load_file("examples/example.hex").unwrap();
+
+    file.for_each_record_mut(|record| {
+        record.data = Bytes::from(vec![0u8; 300]);
+    });
+}
+
+#[test]
+#[should_panic(expected = "record data exceeds 255 bytes")]
+fn record_new_rejects_oversized_data() {
+    Record::new(RecordType::Data, 0, Bytes::from(vec![0u8; 256]));
+}
+
+#[test]
+fn enforce_bank_boundaries_splits_straddling_record() {
+    let bytes: Vec<u8> = (0..32).collect();
+    let mut file = file(&[data(0xFFF0, &bytes), eof()]);
+    let segments = file.segments();
+
+    file.enforce_bank_boundaries();
+
+    assert_eq!(file.segments(), segments);
+    assert_eq!(file.records.len(), 4);
+    assert_eq!(file.records[0].data.len(), 16);
+    assert_eq!(file.records[1].rtype, RecordType::ExtendedLinearAddress);
+    assert_eq!(&file.records[1].data[..], &[0x00, 0x01]);
+    assert_eq!(file.records[2].addr, 0x0000);
+
+    for record in &file.records {
+        assert!(record.addr as usize + record.data.len() <= 0x1_0000);
+        assert_eq!(record.checksum, record.calculate_checksum());
+    }
+}
+
+#[test]
+fn patch_records_addresses_and_checksums() {
+    let file = IntelHexFile::load_file("examples/example.hex").unwrap();
+
+    let records = file.patch_records(0xFFFC, &[1, 2, 3, 4, 5, 6], 16).unwrap();
+
+    let layout: Vec<(RecordType, u16, Vec<u8>)> = records
+        .iter()
+        .map(|r| (r.rtype, r.addr, r.data.to_vec()))
+        .collect();
+
+    assert_eq!(layout, vec![
+        (RecordType::ExtendedLinearAddress, 0, vec![0x00, 0x00]),
+        (RecordType::Data, 0xFFFC, vec![1, 2, 3, 4]),
+        (RecordType::ExtendedLinearAddress, 0, vec![0x00, 0x01]),
+        (RecordType::Data, 0x0000, vec![5, 6])
+    ]);
+
+    for record in &records {
+        assert_eq!(record.checksum, record.calculate_checksum());
+    }
+}
+
+#[test]
+fn patch_records_rejects_address_overflow() {
+    let file = IntelHexFile::load_file("examples/example.hex").unwrap();
+
+    assert!(file.patch_records(0xFFFF_FFFE, &[1, 2, 3, 4], 16).is_err());
+    assert!(file.patch_records(0xFFFF_FFFC, &[1, 2, 3, 4], 16).is_ok());
+}
+
+#[test]
+fn from_binary_rejects_address_overflow() {
+    assert!(IntelHexFile::from_binary(0xFFFF_FFFE, &[1, 2, 3]).is_err());
+
+    let file = IntelHexFile::from_binary(0xFFFF_FFFD, &[1, 2, 3]).unwrap();
+    assert_eq!(file.segments(), vec![(0xFFFF_FFFD, Bytes::from_static(&[1, 2, 3]))]);
+}
+
+#[test]
+fn gap_fill_records_start_each_gap_with_base_record() {
+    let file = file(&[
+        filled(0x00, 0xA0, 4),
+        filled(0x10, 0xA1, 4),
+        filled(0x20, 0xA2, 4),
+        eof()
+    ]);
+
+    let records = file.gap_fill_records(0xFF, 16);
+
+    let layout: Vec<(RecordType, u16, usize)> = records
+        .iter()
+        .map(|r| (r.rtype, r.addr, r.data.len()))
+        .collect();
+
+    assert_eq!(layout, vec![
+        (RecordType::ExtendedLinearAddress, 0, 2),
+        (RecordType::Data, 0x04, 12),
+        (RecordType::ExtendedLinearAddress, 0, 2),
+        (RecordType::Data, 0x14, 12)
+    ]);
+
+    for record in &records {
+        assert_eq!(record.checksum, record.calculate_checksum());
+    }
+}
+
+#[test]
+fn pad_to_covers_region_exactly() {
+    let mut file = file(&[data(0x104, &[1, 2, 3, 4]), data(0x110, &[5, 6]), eof()]);
+
+    file.pad_to(0x100, 0x20, 0xFF).unwrap();
+
+    let (start, binary) = file.to_binary(0xEE);
+    assert_eq!(start, 0x100);
+    assert_eq!(binary.len(), 0x20);
+    assert_eq!(&binary[0x00..0x04], &[0xFF; 4]);
+    assert_eq!(&binary[0x04..0x08], &[1, 2, 3, 4]);
+    assert_eq!(&binary[0x08..0x10], &[0xFF; 8]);
+    assert_eq!(&binary[0x10..0x12], &[5, 6]);
+    assert_eq!(&binary[0x12..0x20], &[0xFF; 14]);
+    assert_eq!(file.records.last().unwrap().rtype, RecordType::EndOfFile);
+
+    let reloaded = IntelHexFile::load(&file.to_hex_str()).unwrap();
+    assert_eq!(reloaded.to_binary(0xEE), (start, binary));
+}
+
+#[test]
+fn pad_to_rejects_data_outside_region() {
+    let mut file = file(&[data(0x104, &[1, 2, 3, 4]), eof()]);
+
+    assert!(file.pad_to(0x105, 0x20, 0xFF).is_err());
+    assert!(file.pad_to(0x100, 0x07, 0xFF).is_err());
+    assert!(file.pad_to(0xFFFF_FFF0, 0x20, 0xFF).is_err());
+    assert_eq!(file.records.len(), 2);
+}
+
+#[test]
+fn pad_to_after_segment_base() {
+    let mut file = file(&[esa(0x1001), data(0x0000, &[1, 2]), eof()]);
+
+    file.pad_to(0x1_0000, 0x40, 0xFF).unwrap();
+
+    let reloaded = IntelHexFile::load(&file.to_hex_str()).unwrap();
+    let (start, binary) = reloaded.to_binary(0xEE);
+    assert_eq!(start, 0x1_0000);
+    assert_eq!(binary.len(), 0x40);
+    assert_eq!(&binary[0x10..0x12], &[1, 2]);
+    assert_eq!(binary.iter().filter(|b| **b == 0xFF).count(), 0x3E);
+}
+
+#[test]
+fn redundant_control_records_found() {
+    let file = file(&[
+        ela(0x0000),
+        data(0x00, &[1]),
+        ela(0x0001),
+        ela(0x0002),
+        data(0x00, &[2]),
+        start_linear(0x0002_0000),
+        eof(),
+        start_linear(0x0002_0000),
+        ela(0x0003),
+        eof()
+    ]);
+
+    assert_eq!(file.redundant_control_records(), vec![0, 2, 6, 7, 8]);
+}
+
+#[test]
+fn redundant_control_records_none_in_minimal_file() {
+    let file = file(&[ela(0x0001), data(0x00, &[1]), start_linear(0x0001_0000), eof()]);
+
+    assert!(file.redundant_control_records().is_empty());
+}
+
+#[test]
+fn strip_below_trims_straddling_record() {
+    let high: Vec<u8> = (0x80..0x90).collect();
+    let straddling: Vec<u8> = (0..16).collect();
+    let mut file = file(&[
+        filled(0x00, 0xB0, 16),
+        data(0xF8, &straddling),
+        ela(0x0001),
+        data(0x10, &high),
+        start_linear(0x0000_0100),
+        eof()
+    ]);
+    let kept: Vec<(u32, u8)> = file.bytes().filter(|(addr, _)| *addr >= 0x100).collect();
+
+    file.strip_below(0x100);
+
+    assert_eq!(file.bytes().collect::<Vec<_>>(), kept);
+    assert_eq!(file.address_span(), (0x100, 0x1_001F));
+    assert_eq!(file.records[0].addr, 0x100);
+    assert_eq!(&file.records[0].data[..], &straddling[8..]);
+    assert_eq!(file.records.last().unwrap().rtype, RecordType::EndOfFile);
+
+    let reloaded = IntelHexFile::load(&file.to_hex_str()).unwrap();
+    assert_eq!(reloaded.bytes().collect::<Vec<_>>(), kept);
+    assert_eq!(reloaded.start_address(), Some(0x100));
+}
+
+#[test]
+fn enforce_bank_boundaries_keeps_segment_base_records() {
+    let bytes: Vec<u8> = (0..32).collect();
+    let mut file = file(&[
+        esa(0x0FFF),
+        data(0x0000, &bytes),
+        data(0x0100, &[0xAA, 0xBB]),
+        eof()
+    ]);
+    let segments = file.segments();
+
+    file.enforce_bank_boundaries();
+
+    let layout: Vec<(RecordType, u16, usize)> = file.records
+        .iter()
+        .map(|r| (r.rtype, r.addr, r.data.len()))
+        .collect();
+
+    assert_eq!(layout, vec![
+        (RecordType::ExtendedSegmentAddress, 0, 2),
+        (RecordType::Data, 0x0000, 16),
+        (RecordType::ExtendedLinearAddress, 0, 2),
+        (RecordType::Data, 0x0000, 16),
+        (RecordType::ExtendedSegmentAddress, 0, 2),
+        (RecordType::Data, 0x0100, 2),
+        (RecordType::EndOfFile, 0, 0)
+    ]);
+    assert_eq!(&file.records[4].data[..], &[0x0F, 0xFF]);
+    assert_eq!(file.segments(), segments);
+}
+
+#[test]
+fn enforce_bank_boundaries_leaves_other_records_untouched() {
+    let mut file = file(&[esa(0x1000), data(0x0000, &[1, 2]), eof()]);
+    let before = file.to_hex_str();
+
+    file.enforce_bank_boundaries();
+
+    assert_eq!(file.to_hex_str(), before);
+}
@@ -0,0 +1,64 @@
+mod common;
+
+use common::{data, ela, eof, file};
+
+use intelhex::{Endian, IntelHexFile};
+
+#[test]
+fn read_words_in_both_endians() {
+    let file = file(&[data(0x10, &[0x11, 0x22, 0x33, 0x44]), data(0x12, &[0x99]), eof()]);
+
+    assert_eq!(file.read_u32(0x10, Endian::Little), Some(0x4499_2211));
+    assert_eq!(file.read_u32(0x10, Endian::Big), Some(0x1122_9944));
+    assert_eq!(file.read_u16(0x12, Endian::Little), Some(0x4499));
+    assert_eq!(file.read_u16(0x12, Endian::Big), Some(0x9944));
+}
+
+#[test]
+fn read_words_require_every_byte() {
+    let file = file(&[data(0x10, &[0x11, 0x22, 0x33, 0x44]), eof()]);
+
+    assert_eq!(file.read_u16(0x13, Endian::Little), None);
+    assert_eq!(file.read_u32(0x0F, Endian::Little), None);
+}
+
+#[test]
+fn read_words_stop_at_top_of_address_space() {
+    let file = file(&[ela(0xFFFF), data(0xFFFE, &[0xAB, 0xCD]), eof()]);
+
+    assert_eq!(file.read_u16(0xFFFF_FFFE, Endian::Big), Some(0xABCD));
+    assert_eq!(file.byte_at(0xFFFF_FFFF), Some(0xCD));
+    assert_eq!(file.read_u16(0xFFFF_FFFF, Endian::Big), None);
+    assert_eq!(file.read_u32(0xFFFF_FFFE, Endian::Big), None);
+}
+
+#[test]
+fn write_words_patch_overlapping_records() {
+    let mut file = file(&[data(0x10, &[0; 8]), data(0x12, &[1; 4]), eof()]);
+
+    file.write_u32(0x12, 0xAABB_CCDD, Endian::Big).unwrap();
+    file.write_u16(0x16, 0x1234, Endian::Little).unwrap();
+
+    assert_eq!(&file.records[0].data[..], &[0, 0, 0xAA, 0xBB, 0xCC, 0xDD, 0x34, 0x12]);
+    assert_eq!(&file.records[1].data[..], &[0xAA, 0xBB, 0xCC, 0xDD]);
+
+    let reloaded = IntelHexFile::load(&file.to_hex_str()).unwrap();
+    assert_eq!(reloaded.read_u32(0x12, Endian::Big), Some(0xAABB_CCDD));
+    assert_eq!(reloaded.read_u16(0x16, Endian::Little), Some(0x1234));
+
+    for record in &reloaded.records {
+        assert_eq!(record.checksum, record.calculate_checksum());
+    }
+}
+
+#[test]
+fn write_words_reject_unpopulated_targets() {
+    let mut file = file(&[data(0x10, &[0; 4]), eof()]);
+    let before = file.to_hex_str();
+
+    let err = file.write_u32(0x12, 0xAABB_CCDD, Endian::Little).err().unwrap();
+
+    assert!(format!("{:?}", err).contains("ImageUnpopulated"));
+    assert_eq!(file.to_hex_str(), before);
+    assert!(file.write_u16(0xFFFF_FFFF, 0, Endian::Little).is_err());
+}